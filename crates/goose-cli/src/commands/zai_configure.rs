@@ -3,6 +3,9 @@ use cliclack::{input, intro, outro, select};
 use console::style;
 use goose::config::Config;
 use goose::model::ModelConfig;
+use goose::providers::zai::{ApiFormat, UsageLogEntry, ZaiProfile};
+use std::collections::BTreeMap;
+use std::io::BufRead;
 
 pub async fn handle_zai_configure() -> Result<()> {
     let config = Config::global();
@@ -177,6 +180,189 @@ pub async fn handle_config_key() -> Result<()> {
     Ok(())
 }
 
+pub async fn handle_zai_profile_add(
+    name: String,
+    host: String,
+    model: Option<String>,
+    format: Option<String>,
+) -> Result<()> {
+    let config = Config::global();
+    let format: Option<ApiFormat> = format
+        .map(|f| f.parse())
+        .transpose()
+        .map_err(|e| anyhow::anyhow!("{}", e))?;
+
+    intro(style(format!(" Add Z.ai profile '{}' ", name)).on_cyan().black())?;
+
+    let key: String = input("Enter the Z.ai API key for this profile:")
+        .validate(|input: &String| {
+            if input.trim().is_empty() {
+                Err("API key cannot be empty")
+            } else {
+                Ok(())
+            }
+        })
+        .interact()?;
+    config.set_secret(&ZaiProfile::secret_key(&name), &key)?;
+
+    let mut profiles = ZaiProfile::list()?;
+    profiles.retain(|p| p.name != name);
+    profiles.push(ZaiProfile {
+        name: name.clone(),
+        host,
+        model,
+        format,
+    });
+    ZaiProfile::save_all(&profiles)?;
+
+    outro(format!("✓ Profile '{}' saved. Use 'zai config profile use {}' to activate it.", name, name))?;
+    Ok(())
+}
+
+pub async fn handle_zai_profile_list() -> Result<()> {
+    let profiles = ZaiProfile::list()?;
+    let active: Option<String> = Config::global().get_param(ZaiProfile::ACTIVE_PROFILE_KEY).ok();
+
+    if profiles.is_empty() {
+        println!("No Z.ai profiles configured yet. Add one with 'zai config profile add'.");
+        return Ok(());
+    }
+
+    println!("\n{} Z.ai Profiles", style("🔧").blue());
+    println!("{}", "─".repeat(30));
+    for profile in &profiles {
+        let marker = if active.as_deref() == Some(profile.name.as_str()) {
+            style("*").green()
+        } else {
+            style(" ").dim()
+        };
+        let format = match profile.format {
+            Some(ApiFormat::Anthropic) => "anthropic",
+            Some(ApiFormat::Openai) => "openai",
+            None => "default",
+        };
+        println!(
+            "{} {} — {} ({}, {})",
+            marker,
+            profile.name,
+            profile.host,
+            profile.model.as_deref().unwrap_or("default"),
+            format
+        );
+    }
+    Ok(())
+}
+
+pub async fn handle_zai_profile_use(name: String) -> Result<()> {
+    let config = Config::global();
+    let profiles = ZaiProfile::list()?;
+
+    if !profiles.iter().any(|p| p.name == name) {
+        return Err(anyhow::anyhow!("Unknown Z.ai profile: {}", name));
+    }
+
+    config.set_param(ZaiProfile::ACTIVE_PROFILE_KEY, &name)?;
+    println!("{} Active Z.ai profile set to: {}", style("✓").green(), name);
+    Ok(())
+}
+
+#[derive(Default)]
+struct ModelTotals {
+    calls: u64,
+    input_tokens: i64,
+    output_tokens: i64,
+    total_tokens: i64,
+}
+
+/// Parse one NDJSON usage log generation into `totals`, returning how many
+/// lines were malformed and skipped. Missing files are treated as empty
+/// (the rotated generation may not exist yet) rather than an error.
+fn accumulate_usage_log(
+    path: &std::path::Path,
+    totals: &mut BTreeMap<String, ModelTotals>,
+) -> Result<u64> {
+    let file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(anyhow::anyhow!("Could not open usage log at {:?}: {}", path, e)),
+    };
+
+    let mut skipped = 0u64;
+    for line in std::io::BufReader::new(file).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: UsageLogEntry = match serde_json::from_str(&line) {
+            Ok(entry) => entry,
+            Err(_) => {
+                skipped += 1;
+                continue;
+            }
+        };
+        let model_totals = totals.entry(entry.model).or_default();
+        model_totals.calls += 1;
+        model_totals.input_tokens += entry.input_tokens as i64;
+        model_totals.output_tokens += entry.output_tokens as i64;
+        model_totals.total_tokens += entry.total_tokens as i64;
+    }
+    Ok(skipped)
+}
+
+pub async fn handle_usage_show() -> Result<()> {
+    let config = Config::global();
+    let log_path: String = config
+        .get_param("ZAI_USAGE_LOG")
+        .map_err(|_| anyhow::anyhow!("ZAI_USAGE_LOG is not configured; set it to the path of the usage log"))?;
+    let log_path = std::path::Path::new(&log_path);
+    let rotated_path = log_path.with_extension("log.1");
+
+    if !log_path.exists() && !rotated_path.exists() {
+        return Err(anyhow::anyhow!(
+            "Could not open usage log at {:?}: no such file",
+            log_path
+        ));
+    }
+
+    let mut totals: BTreeMap<String, ModelTotals> = BTreeMap::new();
+    // Usage is rotated out to the same path with its extension replaced by
+    // `log.1` (`usage.jsonl` -> `usage.log.1`) once the live log grows past
+    // USAGE_LOG_MAX_BYTES; read it too so a report right after rotation
+    // doesn't silently drop everything before it.
+    let skipped = accumulate_usage_log(&rotated_path, &mut totals)?
+        + accumulate_usage_log(log_path, &mut totals)?;
+    if skipped > 0 {
+        println!(
+            "{} Skipped {} malformed usage log line(s)",
+            style("⚠").yellow(),
+            skipped
+        );
+    }
+    if rotated_path.exists() {
+        println!(
+            "{} Includes one rotated generation ({:?}); older usage has been discarded",
+            style("ℹ").blue(),
+            rotated_path
+        );
+    }
+
+    println!("\n{} Z.ai Usage", style("📊").blue());
+    println!("{}", "─".repeat(60));
+    println!(
+        "{:<20} {:>8} {:>12} {:>12} {:>12}",
+        "Model", "Calls", "Input", "Output", "Total"
+    );
+    for (model, model_totals) in &totals {
+        println!(
+            "{:<20} {:>8} {:>12} {:>12} {:>12}",
+            model, model_totals.calls, model_totals.input_tokens, model_totals.output_tokens, model_totals.total_tokens
+        );
+    }
+    println!();
+
+    Ok(())
+}
+
 async fn test_zai_config(api_key: &str, model: &str) -> Result<()> {
     use goose::conversation::message::Message;
     use goose::providers::{create, providers};
@@ -0,0 +1,150 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde_json::Value;
+
+/// How a request authenticates against the provider's host.
+#[derive(Debug, Clone)]
+pub enum AuthMethod {
+    /// A static header, e.g. `x-api-key: <key>`.
+    ApiKey { header_name: String, key: String },
+    /// `Authorization: Bearer <token>`.
+    BearerToken(String),
+}
+
+/// A thin wrapper around `reqwest::Client` shared by the HTTP-based
+/// providers, carrying the base host, auth method, and any extra headers
+/// that need to go out with every request.
+#[derive(Debug)]
+pub struct ApiClient {
+    base_url: String,
+    auth: AuthMethod,
+    timeout: Duration,
+    connect_timeout: Option<Duration>,
+    proxy: Option<String>,
+    extra_headers: HeaderMap,
+    client: reqwest::Client,
+}
+
+impl ApiClient {
+    pub fn with_timeout(host: impl Into<String>, auth: AuthMethod, timeout: Duration) -> Result<Self> {
+        let client = Self::build_client(timeout, None, None)?;
+        Ok(Self {
+            base_url: host.into(),
+            auth,
+            timeout,
+            connect_timeout: None,
+            proxy: None,
+            extra_headers: HeaderMap::new(),
+            client,
+        })
+    }
+
+    /// Bound how long the initial TCP/TLS handshake may take, separately
+    /// from the overall request timeout, so slow links fail fast without
+    /// cutting off a response that's merely taking a while to complete.
+    pub fn with_connect_timeout(mut self, connect_timeout: Duration) -> Result<Self> {
+        self.connect_timeout = Some(connect_timeout);
+        self.client = Self::build_client(self.timeout, self.connect_timeout, self.proxy.as_deref())?;
+        Ok(self)
+    }
+
+    /// Route requests through an HTTP/HTTPS/SOCKS5 proxy.
+    pub fn with_proxy(mut self, proxy_url: &str) -> Result<Self> {
+        self.proxy = Some(proxy_url.to_string());
+        self.client = Self::build_client(self.timeout, self.connect_timeout, self.proxy.as_deref())?;
+        Ok(self)
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Result<Self> {
+        let header_name = HeaderName::from_bytes(name.as_bytes())?;
+        let header_value = HeaderValue::from_str(value)?;
+        self.extra_headers.insert(header_name, header_value);
+        Ok(self)
+    }
+
+    fn build_client(
+        timeout: Duration,
+        connect_timeout: Option<Duration>,
+        proxy: Option<&str>,
+    ) -> Result<reqwest::Client> {
+        let mut builder = reqwest::Client::builder().timeout(timeout);
+        if let Some(connect_timeout) = connect_timeout {
+            builder = builder.connect_timeout(connect_timeout);
+        }
+        if let Some(proxy_url) = proxy {
+            builder = builder.proxy(reqwest::Proxy::all(proxy_url)?);
+        }
+        Ok(builder.build()?)
+    }
+
+    pub async fn response_post(&self, path: &str, payload: &Value) -> Result<reqwest::Response> {
+        let url = format!(
+            "{}/{}",
+            self.base_url.trim_end_matches('/'),
+            path.trim_start_matches('/')
+        );
+
+        let mut request = self.client.post(url).json(payload);
+        request = match &self.auth {
+            AuthMethod::ApiKey { header_name, key } => request.header(header_name, key),
+            AuthMethod::BearerToken(token) => request.bearer_auth(token),
+        };
+        for (name, value) in self.extra_headers.iter() {
+            request = request.header(name, value);
+        }
+
+        Ok(request.send().await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auth() -> AuthMethod {
+        AuthMethod::ApiKey {
+            header_name: "x-api-key".to_string(),
+            key: "test-key".to_string(),
+        }
+    }
+
+    #[test]
+    fn with_connect_timeout_sets_field_and_rebuilds_client() {
+        let client = ApiClient::with_timeout("https://example.com", auth(), Duration::from_secs(30))
+            .unwrap()
+            .with_connect_timeout(Duration::from_secs(5))
+            .unwrap();
+        assert_eq!(client.connect_timeout, Some(Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn with_proxy_sets_field_and_rebuilds_client() {
+        let client = ApiClient::with_timeout("https://example.com", auth(), Duration::from_secs(30))
+            .unwrap()
+            .with_proxy("http://127.0.0.1:8080")
+            .unwrap();
+        assert_eq!(client.proxy.as_deref(), Some("http://127.0.0.1:8080"));
+    }
+
+    #[test]
+    fn with_proxy_and_connect_timeout_compose() {
+        let client = ApiClient::with_timeout("https://example.com", auth(), Duration::from_secs(30))
+            .unwrap()
+            .with_connect_timeout(Duration::from_secs(5))
+            .unwrap()
+            .with_proxy("socks5://127.0.0.1:1080")
+            .unwrap();
+        assert_eq!(client.connect_timeout, Some(Duration::from_secs(5)));
+        assert_eq!(client.proxy.as_deref(), Some("socks5://127.0.0.1:1080"));
+    }
+
+    #[test]
+    fn with_proxy_rejects_invalid_proxy_url() {
+        let result = ApiClient::with_timeout("https://example.com", auth(), Duration::from_secs(30))
+            .unwrap()
+            .with_proxy("not a url");
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,14 @@
+use std::time::Duration;
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ProviderError {
+    #[error("Request failed: {0}")]
+    RequestFailed(String),
+
+    /// A 429/overloaded response. `retry_after` carries the server-supplied
+    /// backoff delay when one was present in the response headers.
+    #[error("Rate limited by provider")]
+    RateLimited { retry_after: Option<Duration> },
+}
@@ -0,0 +1,43 @@
+use std::future::Future;
+use std::time::Duration;
+
+use super::errors::ProviderError;
+
+const MAX_RETRIES: u32 = 3;
+const BASE_BACKOFF: Duration = Duration::from_secs(2);
+
+/// Retries a provider call up to `MAX_RETRIES` times when it fails with
+/// `ProviderError::RateLimited`; any other error is returned at once. Each
+/// retry sleeps for the server-supplied `retry_after` when present, falling
+/// back to exponential backoff otherwise, so we honor a server's requested
+/// delay instead of hammering it on our own schedule.
+#[async_trait::async_trait]
+pub trait ProviderRetry {
+    async fn with_retry<F, Fut, T>(&self, f: F) -> Result<T, ProviderError>
+    where
+        F: Fn() -> Fut + Send + Sync,
+        Fut: Future<Output = Result<T, ProviderError>> + Send,
+        T: Send,
+    {
+        let mut attempt = 0;
+        loop {
+            match f().await {
+                Ok(value) => return Ok(value),
+                Err(ProviderError::RateLimited { retry_after }) if attempt < MAX_RETRIES => {
+                    let delay = retry_after.unwrap_or(BASE_BACKOFF * 2u32.pow(attempt));
+                    tracing::warn!(
+                        "rate limited, retrying in {:?} (attempt {}/{})",
+                        delay,
+                        attempt + 1,
+                        MAX_RETRIES
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl<P: Sync> ProviderRetry for P {}
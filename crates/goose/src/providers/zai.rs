@@ -10,9 +10,8 @@ use tokio_util::io::StreamReader;
 use super::api_client::{ApiClient, AuthMethod};
 use super::base::{ConfigKey, MessageStream, ModelInfo, Provider, ProviderMetadata, ProviderUsage};
 use super::errors::ProviderError;
-use super::formats::anthropic::{
-    create_request, get_usage, response_to_message, response_to_streaming_message,
-};
+use super::formats::anthropic;
+use super::formats::openai;
 use super::retry::ProviderRetry;
 use super::utils::handle_status_openai_compat;
 use crate::conversation::message::Message;
@@ -30,51 +29,295 @@ pub const ZAI_KNOWN_MODELS: &[(&str, usize)] = &[
 
 pub const ZAI_DOC_URL: &str = "https://z.ai/docs";
 
+const ZAI_ANTHROPIC_PATH: &str = "api/anthropic/v1/messages";
+const ZAI_OPENAI_PATH: &str = "api/paas/v4/chat/completions";
+
+/// Rotate the usage log once it crosses this size, so `ZAI_USAGE_LOG` doesn't
+/// grow unbounded over the life of a long-running install.
+const USAGE_LOG_MAX_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Which request/response dialect to speak with the configured host.
+/// Z.ai exposes both; some self-hosted gateways only proxy one of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ApiFormat {
+    Anthropic,
+    Openai,
+}
+
+impl std::str::FromStr for ApiFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "anthropic" => Ok(Self::Anthropic),
+            "openai" => Ok(Self::Openai),
+            other => Err(anyhow::anyhow!("Unknown ZAI_API_FORMAT: {}", other)),
+        }
+    }
+}
+
+/// A named Z.ai endpoint, letting a user switch between e.g. a paid cloud
+/// key and a self-hosted GLM gateway without re-entering config each time.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ZaiProfile {
+    pub name: String,
+    pub host: String,
+    pub model: Option<String>,
+    /// Per-profile API dialect override. Lets e.g. a cloud profile stay on
+    /// the Anthropic-compatible format while a self-hosted profile that only
+    /// speaks the OpenAI dialect sets its own, independent of the global
+    /// `ZAI_API_FORMAT`.
+    #[serde(default)]
+    pub format: Option<ApiFormat>,
+}
+
+impl ZaiProfile {
+    /// Config param key that stores the list of known profiles, as JSON.
+    pub const PROFILES_KEY: &'static str = "ZAI_PROFILES";
+    /// Config param key that stores the name of the active profile.
+    pub const ACTIVE_PROFILE_KEY: &'static str = "ZAI_ACTIVE_PROFILE";
+
+    /// Secret key under which this profile's API key is stored.
+    pub fn secret_key(name: &str) -> String {
+        format!("ZAI_API_KEY__{}", name)
+    }
+
+    pub fn list() -> Result<Vec<ZaiProfile>> {
+        let config = crate::config::Config::global();
+        Ok(config.get_param(Self::PROFILES_KEY).unwrap_or_default())
+    }
+
+    pub fn save_all(profiles: &[ZaiProfile]) -> Result<()> {
+        let config = crate::config::Config::global();
+        config.set_param(Self::PROFILES_KEY, serde_json::to_value(profiles)?)?;
+        Ok(())
+    }
+}
+
+/// One row of the `ZAI_USAGE_LOG` newline-delimited JSON file, written after
+/// every completed request so `zai usage` can report totals without needing
+/// an external telemetry service. `session_id` and `timestamp` let usage be
+/// aggregated per session or time window instead of only as one flat
+/// lifetime total.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct UsageLogEntry {
+    pub session_id: String,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub model: String,
+    pub input_tokens: i32,
+    pub output_tokens: i32,
+    pub total_tokens: i32,
+    pub duration_ms: u128,
+    pub streamed: bool,
+}
+
+impl UsageLogEntry {
+    /// Append a single entry to the log, serialized against other in-process
+    /// writers so concurrent requests (e.g. parallel tool calls) can't
+    /// interleave partial lines into the NDJSON file. Rotates the file out
+    /// of the way first if it's grown past `USAGE_LOG_MAX_BYTES`.
+    fn append(path: &std::path::Path, entry: &UsageLogEntry) -> Result<()> {
+        Self::append_with_limit(path, entry, USAGE_LOG_MAX_BYTES)
+    }
+
+    fn append_with_limit(path: &std::path::Path, entry: &UsageLogEntry, max_bytes: u64) -> Result<()> {
+        use std::io::Write;
+        static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+        let _guard = LOCK.lock().unwrap();
+
+        if let Ok(metadata) = std::fs::metadata(path) {
+            if metadata.len() > max_bytes {
+                let rotated = path.with_extension("log.1");
+                let _ = std::fs::rename(path, rotated);
+            }
+        }
+
+        let line = format!("{}\n", serde_json::to_string(entry)?);
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        file.write_all(line.as_bytes())?;
+        Ok(())
+    }
+}
+
 #[derive(Debug, serde::Serialize)]
 pub struct ZaiProvider {
     #[serde(skip)]
     api_client: ApiClient,
     model: ModelConfig,
     name: String,
+    stream_idle_timeout: std::time::Duration,
+    format: ApiFormat,
+    usage_log_path: Option<std::path::PathBuf>,
+    /// Identifies every usage log entry written by this provider instance.
+    /// `zai usage` totals entries by model only; it does not group them by
+    /// session.
+    session_id: String,
+}
+
+/// A short, process-unique id for tagging usage log entries with the
+/// session that produced them.
+fn new_session_id() -> String {
+    static COUNTER: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+    let n = COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    format!("{:x}-{:x}", std::process::id(), n)
 }
 
 impl ZaiProvider {
     pub async fn from_env(model: ModelConfig) -> Result<Self> {
-        let model = model.with_fast(ZAI_DEFAULT_FAST_MODEL.to_string());
-
         let config = crate::config::Config::global();
+
+        // If the user has selected a named profile (via `zai config profile
+        // use <name>`), it takes precedence over the single-profile env vars.
+        if let Ok(active_profile) = config.get_param::<String>(ZaiProfile::ACTIVE_PROFILE_KEY) {
+            if !active_profile.is_empty() {
+                // `model` here is whatever the generic provider factory
+                // resolved (GOOSE_MODEL if the user set it, otherwise some
+                // provider-agnostic default). Only treat it as an explicit
+                // override if GOOSE_MODEL was actually configured; otherwise
+                // let the profile's own default model win, same as it
+                // already does for host/key.
+                let explicit_model = config
+                    .get_param::<String>("GOOSE_MODEL")
+                    .ok()
+                    .map(|_| model);
+                return Self::from_profile(&active_profile, explicit_model).await;
+            }
+        }
+
         let api_key: String = config.get_secret("ZAI_API_KEY")?;
         let host: String = config
             .get_param("ZAI_HOST")
             .unwrap_or_else(|_| "https://api.z.ai".to_string());
+
+        Self::build("zai", host, api_key, model, None).await
+    }
+
+    /// Build a provider from one of the named profiles saved via
+    /// `zai config profile add`, falling back to the profile's default
+    /// model when `model` isn't overridden.
+    pub async fn from_profile(name: &str, model: Option<ModelConfig>) -> Result<Self> {
+        let profile = ZaiProfile::list()?
+            .into_iter()
+            .find(|p| p.name == name)
+            .ok_or_else(|| anyhow::anyhow!("Unknown Z.ai profile: {}", name))?;
+
+        let config = crate::config::Config::global();
+        let api_key: String = config.get_secret(&ZaiProfile::secret_key(&profile.name))?;
+        let model = resolve_profile_model(&profile, model)?;
+
+        Self::build(&profile.name, profile.host, api_key, model, profile.format).await
+    }
+
+    async fn build(
+        name: &str,
+        host: String,
+        api_key: String,
+        model: ModelConfig,
+        format_override: Option<ApiFormat>,
+    ) -> Result<Self> {
+        let model = model.with_fast(ZAI_DEFAULT_FAST_MODEL.to_string());
+
+        let config = crate::config::Config::global();
         let timeout_secs: u64 = config.get_param("ZAI_TIMEOUT").unwrap_or(600);
+        let connect_timeout_secs: u64 = config.get_param("ZAI_CONNECT_TIMEOUT").unwrap_or(10);
+        let stream_idle_timeout_secs: u64 =
+            config.get_param("ZAI_STREAM_IDLE_TIMEOUT").unwrap_or(300);
+        let proxy: Option<String> = config.get_param("ZAI_PROXY").ok().or_else(|| {
+            std::env::var("HTTPS_PROXY")
+                .ok()
+                .or_else(|| std::env::var("ALL_PROXY").ok())
+        });
+        // A profile's own format wins over the global ZAI_API_FORMAT, same
+        // as its host/key already do, so a self-hosted OpenAI-only profile
+        // and an Anthropic-compatible cloud profile can coexist.
+        let format: ApiFormat = match format_override {
+            Some(format) => format,
+            None => config
+                .get_param::<String>("ZAI_API_FORMAT")
+                .ok()
+                .map(|v| v.parse())
+                .transpose()?
+                .unwrap_or(ApiFormat::Anthropic),
+        };
+        let usage_log_path: Option<std::path::PathBuf> = config
+            .get_param::<String>("ZAI_USAGE_LOG")
+            .ok()
+            .map(std::path::PathBuf::from);
 
-        // Use x-api-key header for Anthropic-compatible API
-        let auth = AuthMethod::ApiKey {
-            header_name: "x-api-key".to_string(),
-            key: api_key,
+        let auth = match format {
+            ApiFormat::Anthropic => AuthMethod::ApiKey {
+                header_name: "x-api-key".to_string(),
+                key: api_key,
+            },
+            ApiFormat::Openai => AuthMethod::BearerToken(api_key),
         };
-        
+
         let mut api_client =
-            ApiClient::with_timeout(host, auth, std::time::Duration::from_secs(timeout_secs))?;
-        
-        api_client = api_client.with_header("anthropic-version", "2023-06-01")?;
+            ApiClient::with_timeout(host, auth, std::time::Duration::from_secs(timeout_secs))?
+                .with_connect_timeout(std::time::Duration::from_secs(connect_timeout_secs))?;
+
+        if let Some(proxy) = proxy {
+            api_client = api_client.with_proxy(&proxy)?;
+        }
+
+        if format == ApiFormat::Anthropic {
+            api_client = api_client.with_header("anthropic-version", "2023-06-01")?;
+        }
 
         Ok(Self {
             api_client,
             model,
-            name: "zai".to_string(),
+            name: name.to_string(),
+            stream_idle_timeout: std::time::Duration::from_secs(stream_idle_timeout_secs),
+            format,
+            usage_log_path,
+            session_id: new_session_id(),
         })
     }
 
+    fn endpoint_path(&self) -> &'static str {
+        match self.format {
+            ApiFormat::Anthropic => ZAI_ANTHROPIC_PATH,
+            ApiFormat::Openai => ZAI_OPENAI_PATH,
+        }
+    }
+
+    fn log_usage(&self, model: &str, usage: &ProviderUsage, duration: std::time::Duration, streamed: bool) {
+        let Some(path) = &self.usage_log_path else {
+            return;
+        };
+        let entry = UsageLogEntry {
+            session_id: self.session_id.clone(),
+            timestamp: chrono::Utc::now(),
+            model: model.to_string(),
+            input_tokens: usage.usage.input_tokens.unwrap_or(0),
+            output_tokens: usage.usage.output_tokens.unwrap_or(0),
+            total_tokens: usage.usage.total_tokens.unwrap_or(0),
+            duration_ms: duration.as_millis(),
+            streamed,
+        };
+        if let Err(e) = UsageLogEntry::append(path, &entry) {
+            tracing::warn!("failed to write ZAI_USAGE_LOG entry: {}", e);
+        }
+    }
+
     async fn post(&self, payload: &Value) -> Result<Value, ProviderError> {
         let response = self
             .api_client
-            .response_post("api/anthropic/v1/messages", payload)
+            .response_post(self.endpoint_path(), payload)
             .await
             .map_err(|e| ProviderError::RequestFailed(e.to_string()))?;
 
         let status = response.status();
+
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.as_u16() == 529 {
+            let retry_after = retry_after_from_headers(response.headers());
+            return Err(ProviderError::RateLimited { retry_after });
+        }
+
         let body = response
             .text()
             .await
@@ -92,6 +335,65 @@ impl ZaiProvider {
     }
 }
 
+/// Pull the next item off a streaming response, failing with
+/// `ProviderError::RequestFailed("stream stalled")` if the server goes
+/// quiet for longer than `idle_timeout` instead of hanging forever.
+async fn next_or_stall<S>(
+    idle_timeout: std::time::Duration,
+    stream: &mut S,
+) -> Result<Option<S::Item>, ProviderError>
+where
+    S: futures::Stream + Unpin,
+{
+    tokio::time::timeout(idle_timeout, futures::StreamExt::next(stream))
+        .await
+        .map_err(|_| ProviderError::RequestFailed("stream stalled".to_string()))
+}
+
+/// Resolve which model a profile activation should use: an explicit
+/// override (e.g. `GOOSE_MODEL` set by the user) always wins; otherwise the
+/// profile's own default model; otherwise the provider-wide default.
+fn resolve_profile_model(profile: &ZaiProfile, explicit: Option<ModelConfig>) -> Result<ModelConfig> {
+    match explicit {
+        Some(model) => Ok(model),
+        None => {
+            let model_name = profile
+                .model
+                .clone()
+                .unwrap_or_else(|| ZAI_DEFAULT_MODEL.to_string());
+            ModelConfig::new(&model_name)
+        }
+    }
+}
+
+/// Parse a server-supplied retry delay from the `Retry-After` header (either
+/// a delta in seconds or an HTTP-date) or the Anthropic-style
+/// `anthropic-ratelimit-requests-reset` header (an RFC3339 timestamp), so we
+/// back off for as long as the server actually asked instead of guessing.
+fn retry_after_from_headers(headers: &reqwest::header::HeaderMap) -> Option<std::time::Duration> {
+    if let Some(value) = headers
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+    {
+        if let Ok(secs) = value.parse::<u64>() {
+            return Some(std::time::Duration::from_secs(secs));
+        }
+        if let Ok(date) = httpdate::parse_http_date(value) {
+            if let Ok(remaining) = date.duration_since(std::time::SystemTime::now()) {
+                return Some(remaining);
+            }
+        }
+    }
+
+    let reset_at = headers
+        .get("anthropic-ratelimit-requests-reset")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| chrono::DateTime::parse_from_rfc3339(v).ok())?;
+    (reset_at.with_timezone(&chrono::Utc) - chrono::Utc::now())
+        .to_std()
+        .ok()
+}
+
 #[async_trait]
 impl Provider for ZaiProvider {
     fn metadata() -> ProviderMetadata {
@@ -110,6 +412,11 @@ impl Provider for ZaiProvider {
                 ConfigKey::new("ZAI_API_KEY", true, true, None),
                 ConfigKey::new("ZAI_HOST", false, false, Some("https://api.z.ai")),
                 ConfigKey::new("ZAI_TIMEOUT", false, false, Some("600")),
+                ConfigKey::new("ZAI_CONNECT_TIMEOUT", false, false, Some("10")),
+                ConfigKey::new("ZAI_PROXY", false, false, None),
+                ConfigKey::new("ZAI_STREAM_IDLE_TIMEOUT", false, false, Some("300")),
+                ConfigKey::new("ZAI_API_FORMAT", false, false, Some("anthropic")),
+                ConfigKey::new("ZAI_USAGE_LOG", false, false, None),
             ],
         )
     }
@@ -133,8 +440,12 @@ impl Provider for ZaiProvider {
         messages: &[Message],
         tools: &[Tool],
     ) -> Result<(Message, ProviderUsage), ProviderError> {
-        let payload = create_request(model_config, system, messages, tools)?;
+        let payload = match self.format {
+            ApiFormat::Anthropic => anthropic::create_request(model_config, system, messages, tools)?,
+            ApiFormat::Openai => openai::create_request(model_config, system, messages, tools)?,
+        };
 
+        let started_at = std::time::Instant::now();
         let mut log = RequestLog::start(&self.model, &payload)?;
         let json_response = self
             .with_retry(|| async {
@@ -146,13 +457,25 @@ impl Provider for ZaiProvider {
                 let _ = log.error(e);
             })?;
 
-        let message = response_to_message(&json_response)
-            .map_err(|e| ProviderError::RequestFailed(e.to_string()))?;
-        let usage = get_usage(&json_response)
-            .map_err(|e| ProviderError::RequestFailed(e.to_string()))?;
+        let (message, usage) = match self.format {
+            ApiFormat::Anthropic => (
+                anthropic::response_to_message(&json_response)
+                    .map_err(|e| ProviderError::RequestFailed(e.to_string()))?,
+                anthropic::get_usage(&json_response)
+                    .map_err(|e| ProviderError::RequestFailed(e.to_string()))?,
+            ),
+            ApiFormat::Openai => (
+                openai::response_to_message(&json_response)
+                    .map_err(|e| ProviderError::RequestFailed(e.to_string()))?,
+                openai::get_usage(&json_response)
+                    .map_err(|e| ProviderError::RequestFailed(e.to_string()))?,
+            ),
+        };
 
         log.write(&json_response, Some(&usage))?;
-        Ok((message, ProviderUsage::new(model_config.model_name.clone(), usage)))
+        let usage = ProviderUsage::new(model_config.model_name.clone(), usage);
+        self.log_usage(&model_config.model_name, &usage, started_at.elapsed(), false);
+        Ok((message, usage))
     }
 
     async fn stream(
@@ -161,43 +484,75 @@ impl Provider for ZaiProvider {
         messages: &[Message],
         tools: &[Tool],
     ) -> Result<MessageStream, ProviderError> {
-        let mut payload = create_request(&self.model, system, messages, tools)
-            .map_err(|e| ProviderError::RequestFailed(e.to_string()))?;
-        
+        let mut payload = match self.format {
+            ApiFormat::Anthropic => anthropic::create_request(&self.model, system, messages, tools),
+            ApiFormat::Openai => openai::create_request(&self.model, system, messages, tools),
+        }
+        .map_err(|e| ProviderError::RequestFailed(e.to_string()))?;
+
         // Enable streaming
         payload
             .as_object_mut()
             .unwrap()
             .insert("stream".to_string(), serde_json::Value::Bool(true));
 
+        let started_at = std::time::Instant::now();
         let mut log = RequestLog::start(&self.model, &payload)?;
 
-        let resp = self
-            .api_client
-            .response_post("api/anthropic/v1/messages", &payload)
+        let response = self
+            .with_retry(|| async {
+                let resp = self
+                    .api_client
+                    .response_post(self.endpoint_path(), &payload)
+                    .await
+                    .map_err(|e| ProviderError::RequestFailed(e.to_string()))?;
+
+                let status = resp.status();
+                if status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.as_u16() == 529 {
+                    let retry_after = retry_after_from_headers(resp.headers());
+                    return Err(ProviderError::RateLimited { retry_after });
+                }
+
+                handle_status_openai_compat(resp).await
+            })
             .await
             .inspect_err(|e| {
                 let _ = log.error(e);
             })?;
 
-        let response = handle_status_openai_compat(resp).await.inspect_err(|e| {
-            let _ = log.error(e);
-        })?;
-
         let stream = response.bytes_stream().map_err(io::Error::other);
+        let idle_timeout = self.stream_idle_timeout;
+        let format = self.format;
 
-        Ok(Box::pin(try_stream! {
-            let stream_reader = StreamReader::new(stream);
-            let framed = tokio_util::codec::FramedRead::new(
-                stream_reader, 
-                tokio_util::codec::LinesCodec::new()
-            ).map_err(anyhow::Error::from);
+        let stream_reader = StreamReader::new(stream);
+        let framed = tokio_util::codec::FramedRead::new(
+            stream_reader,
+            tokio_util::codec::LinesCodec::new(),
+        )
+        .map_err(anyhow::Error::from);
 
-            let message_stream = response_to_streaming_message(framed);
+        // Both dialects decode into the same (Message, Option<ProviderUsage>)
+        // item shape, so box them behind one dynamic stream and share the
+        // idle-timeout/log/usage-logging loop below rather than duplicating
+        // it per format.
+        let message_stream: std::pin::Pin<
+            Box<dyn futures::Stream<Item = anyhow::Result<(Message, Option<ProviderUsage>)>> + Send>,
+        > = match format {
+            ApiFormat::Openai => Box::pin(openai::response_to_streaming_message(framed)),
+            ApiFormat::Anthropic => Box::pin(anthropic::response_to_streaming_message(framed)),
+        };
+
+        Ok(Box::pin(try_stream! {
             pin!(message_stream);
-            while let Some(message) = futures::StreamExt::next(&mut message_stream).await {
+            loop {
+                let Some(message) = next_or_stall(idle_timeout, &mut message_stream).await.inspect_err(|e| {
+                    let _ = log.error(e);
+                })? else { break };
                 let (message, usage) = message.map_err(|e| ProviderError::RequestFailed(format!("Stream decode error: {}", e)))?;
                 log.write(&message, usage.as_ref().map(|f| f.usage).as_ref())?;
+                if let Some(usage) = &usage {
+                    self.log_usage(&self.model.model_name, usage, started_at.elapsed(), true);
+                }
                 yield (message, usage);
             }
         }))
@@ -207,3 +562,193 @@ impl Provider for ZaiProvider {
         true
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn profile(model: Option<&str>) -> ZaiProfile {
+        ZaiProfile {
+            name: "test".to_string(),
+            host: "https://example.com".to_string(),
+            model: model.map(str::to_string),
+            format: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn next_or_stall_times_out_on_a_quiet_stream() {
+        let mut pending = futures::stream::pending::<()>();
+        let result = next_or_stall(std::time::Duration::from_millis(20), &mut pending).await;
+        match result {
+            Err(ProviderError::RequestFailed(msg)) => assert_eq!(msg, "stream stalled"),
+            other => panic!("expected a stream-stalled error, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn next_or_stall_returns_items_before_the_deadline() {
+        let mut ready = futures::stream::iter([1, 2, 3]);
+        let item = next_or_stall(std::time::Duration::from_secs(5), &mut ready)
+            .await
+            .unwrap();
+        assert_eq!(item, Some(1));
+    }
+
+    #[test]
+    fn resolve_profile_model_falls_back_to_default_model() {
+        let resolved = resolve_profile_model(&profile(None), None).unwrap();
+        assert_eq!(resolved.model_name, ZAI_DEFAULT_MODEL);
+    }
+
+    #[test]
+    fn resolve_profile_model_uses_profile_default_when_no_override() {
+        let resolved = resolve_profile_model(&profile(Some("glm-4.6")), None).unwrap();
+        assert_eq!(resolved.model_name, "glm-4.6");
+    }
+
+    #[test]
+    fn resolve_profile_model_explicit_override_wins_over_profile_default() {
+        let explicit = ModelConfig::new("glm-4.5-air").unwrap();
+        let resolved = resolve_profile_model(&profile(Some("glm-4.6")), Some(explicit)).unwrap();
+        assert_eq!(resolved.model_name, "glm-4.5-air");
+    }
+
+    fn temp_usage_log_path(label: &str) -> std::path::PathBuf {
+        static COUNTER: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+        let n = COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        std::env::temp_dir().join(format!(
+            "zai_usage_log_test_{}_{}_{}.jsonl",
+            std::process::id(),
+            label,
+            n
+        ))
+    }
+
+    fn usage_entry(model: &str) -> UsageLogEntry {
+        UsageLogEntry {
+            session_id: "test-session".to_string(),
+            timestamp: chrono::Utc::now(),
+            model: model.to_string(),
+            input_tokens: 1,
+            output_tokens: 2,
+            total_tokens: 3,
+            duration_ms: 10,
+            streamed: false,
+        }
+    }
+
+    #[test]
+    fn usage_log_append_survives_concurrent_writers() {
+        let path = temp_usage_log_path("concurrent");
+        let _ = std::fs::remove_file(&path);
+
+        std::thread::scope(|scope| {
+            for i in 0..8 {
+                let path = path.clone();
+                scope.spawn(move || {
+                    UsageLogEntry::append(&path, &usage_entry(&format!("model-{i}"))).unwrap();
+                });
+            }
+        });
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), 8);
+        for line in lines {
+            serde_json::from_str::<UsageLogEntry>(line)
+                .unwrap_or_else(|e| panic!("corrupted line {:?}: {}", line, e));
+        }
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn usage_log_append_rotates_oversized_file() {
+        let path = temp_usage_log_path("rotate");
+        let rotated = path.with_extension("log.1");
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(&rotated);
+
+        let old_contents = "x".repeat(128);
+        std::fs::write(&path, &old_contents).unwrap();
+
+        UsageLogEntry::append_with_limit(&path, &usage_entry("glm-4.6"), 64).unwrap();
+
+        assert_eq!(std::fs::read_to_string(&rotated).unwrap(), old_contents);
+        let live_contents = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(live_contents.lines().count(), 1);
+        assert!(live_contents.contains("glm-4.6"));
+
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(&rotated);
+    }
+
+    #[test]
+    fn api_format_parses_known_values() {
+        assert_eq!(ApiFormat::from_str("anthropic").unwrap(), ApiFormat::Anthropic);
+        assert_eq!(ApiFormat::from_str("openai").unwrap(), ApiFormat::Openai);
+    }
+
+    #[test]
+    fn api_format_rejects_unknown_values() {
+        assert!(ApiFormat::from_str("cohere").is_err());
+        assert!(ApiFormat::from_str("").is_err());
+    }
+
+    fn headers_from(pairs: &[(&str, &str)]) -> reqwest::header::HeaderMap {
+        let mut headers = reqwest::header::HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(
+                reqwest::header::HeaderName::from_bytes(name.as_bytes()).unwrap(),
+                reqwest::header::HeaderValue::from_str(value).unwrap(),
+            );
+        }
+        headers
+    }
+
+    #[test]
+    fn retry_after_parses_seconds() {
+        let headers = headers_from(&[("retry-after", "30")]);
+        let retry_after = retry_after_from_headers(&headers).unwrap();
+        assert_eq!(retry_after.as_secs(), 30);
+    }
+
+    #[test]
+    fn retry_after_parses_http_date() {
+        let future = httpdate::fmt_http_date(std::time::SystemTime::now() + std::time::Duration::from_secs(120));
+        let headers = headers_from(&[("retry-after", &future)]);
+        let retry_after = retry_after_from_headers(&headers).unwrap();
+        // Allow a little slack for time spent formatting/parsing above.
+        assert!(retry_after.as_secs() > 100 && retry_after.as_secs() <= 120);
+    }
+
+    #[test]
+    fn retry_after_parses_anthropic_reset_header() {
+        let reset_at = chrono::Utc::now() + chrono::Duration::seconds(60);
+        let headers = headers_from(&[(
+            "anthropic-ratelimit-requests-reset",
+            &reset_at.to_rfc3339(),
+        )]);
+        let retry_after = retry_after_from_headers(&headers).unwrap();
+        assert!(retry_after.as_secs() > 50 && retry_after.as_secs() <= 60);
+    }
+
+    #[test]
+    fn retry_after_returns_none_without_relevant_headers() {
+        let headers = headers_from(&[("content-type", "application/json")]);
+        assert!(retry_after_from_headers(&headers).is_none());
+    }
+
+    #[test]
+    fn retry_after_prefers_retry_after_over_anthropic_reset() {
+        let reset_at = chrono::Utc::now() + chrono::Duration::seconds(600);
+        let headers = headers_from(&[
+            ("retry-after", "5"),
+            ("anthropic-ratelimit-requests-reset", &reset_at.to_rfc3339()),
+        ]);
+        let retry_after = retry_after_from_headers(&headers).unwrap();
+        assert_eq!(retry_after.as_secs(), 5);
+    }
+}